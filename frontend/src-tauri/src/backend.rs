@@ -0,0 +1,433 @@
+// Spawns, supervises and cleans up the embedded Python backend process.
+//
+// State lives in a `BackendState` owned by Tauri (`app.manage(..)`) rather
+// than free-floating statics, so it can be looked up from any command or
+// background thread via the `AppHandle`.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager};
+
+use crate::metrics;
+
+// Default loopback port we try first, to keep the "reuse an already-running
+// backend" fast-path working without any extra configuration.
+const DEFAULT_PORT: u16 = 8000;
+
+const MAX_LOG_LINES: usize = 500;
+const MAX_RESTART_ATTEMPTS: usize = 5;
+
+/// Owns everything needed to run and supervise the embedded backend: the
+/// child handle, readiness/port state, the shared HTTP client and the
+/// rolling log buffer. Managed by Tauri via `app.manage(BackendState::new())`.
+pub struct BackendState {
+    child: Mutex<Option<Child>>,
+    started: AtomicBool,
+    port: AtomicU16,
+    shutting_down: AtomicBool,
+    restart_count: AtomicUsize,
+    log_buffer: Mutex<VecDeque<String>>,
+    pub client: reqwest::Client,
+}
+
+impl BackendState {
+    pub fn new() -> Self {
+        Self {
+            child: Mutex::new(None),
+            started: AtomicBool::new(false),
+            port: AtomicU16::new(DEFAULT_PORT),
+            shutting_down: AtomicBool::new(false),
+            restart_count: AtomicUsize::new(0),
+            log_buffer: Mutex::new(VecDeque::new()),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("failed to build shared reqwest client"),
+        }
+    }
+
+    /// Returns the base URL (`http://127.0.0.1:<port>`) of the running backend.
+    pub fn base_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port.load(Ordering::Relaxed))
+    }
+}
+
+impl Default for BackendState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn state(app: &AppHandle) -> tauri::State<'_, BackendState> {
+    app.state::<BackendState>()
+}
+
+/// Convenience accessor for the base URL of the currently running backend.
+pub fn base_url(app: &AppHandle) -> String {
+    state(app).base_url()
+}
+
+#[derive(Clone, serde::Serialize)]
+struct BackendLogEvent {
+    stream: &'static str,
+    line: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct BackendStatusEvent {
+    status: &'static str,
+    detail: Option<String>,
+}
+
+fn push_log_line(app: &AppHandle, stream: &'static str, line: String) {
+    {
+        let mut buf = state(app).log_buffer.lock().unwrap();
+        if buf.len() >= MAX_LOG_LINES {
+            buf.pop_front();
+        }
+        buf.push_back(format!("[{}] {}", stream, line));
+    }
+    let _ = app.emit_all("backend-log", BackendLogEvent { stream, line });
+}
+
+fn emit_status(app: &AppHandle, status: &'static str, detail: Option<String>) {
+    let _ = app.emit_all("backend-status", BackendStatusEvent { status, detail });
+}
+
+// Joins the last `n` captured stdout/stderr lines, for surfacing in error
+// messages once the pipes themselves have already been drained by the log
+// reader threads.
+fn recent_log_lines(app: &AppHandle, n: usize) -> String {
+    let buf = state(app).log_buffer.lock().unwrap();
+    buf.iter()
+        .rev()
+        .take(n)
+        .rev()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Reads `BACKEND_STARTUP_TIMEOUT_SECS` if set, otherwise defaults to 20 seconds.
+fn startup_timeout() -> Duration {
+    std::env::var("BACKEND_STARTUP_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(20))
+}
+
+fn backend_directory() -> Result<std::path::PathBuf, String> {
+    // Get the backend directory path - try multiple possible locations
+    let possible_paths = vec![
+        // Bundled backend path (in app bundle)
+        std::env::current_dir()
+            .unwrap()
+            .join("Contents/Resources/backend"),
+        // Development path
+        std::env::current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("backend"),
+        // Built app path
+        std::env::current_dir().unwrap().join("backend"),
+        // Alternative built app path
+        std::env::current_dir().unwrap().join("../backend"),
+    ];
+
+    possible_paths
+        .into_iter()
+        .find(|path| path.exists())
+        .ok_or_else(|| "Backend directory not found. Please ensure the backend folder exists.".to_string())
+}
+
+fn spawn_backend_process(backend_path: &std::path::Path, port: u16) -> Result<Child, String> {
+    let python_cmd = if cfg!(target_os = "windows") {
+        "python"
+    } else {
+        "python3"
+    };
+
+    Command::new(python_cmd)
+        .arg("-m")
+        .arg("uvicorn")
+        .arg("app.main:app")
+        .arg("--host")
+        .arg("127.0.0.1")
+        .arg("--port")
+        .arg(port.to_string())
+        .current_dir(backend_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            format!(
+                "Failed to start backend: {}. Make sure Python and uvicorn are installed.",
+                e
+            )
+        })
+}
+
+// Picks a port for a fresh backend: the default port if nothing is bound to
+// it yet, otherwise a free ephemeral port handed out by the OS.
+fn pick_port() -> Result<u16, String> {
+    if let Ok(listener) = TcpListener::bind(("127.0.0.1", DEFAULT_PORT)) {
+        drop(listener);
+        return Ok(DEFAULT_PORT);
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", 0))
+        .map_err(|e| format!("Failed to find a free port: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read bound port: {}", e))?
+        .port();
+    drop(listener);
+    Ok(port)
+}
+
+// Drains a stdout/stderr pipe line-by-line, forwarding each line to the ring
+// buffer and the frontend until the pipe closes (i.e. the process exits).
+fn spawn_log_reader<R: std::io::Read + Send + 'static>(
+    app: AppHandle,
+    stream: &'static str,
+    pipe: R,
+) {
+    thread::spawn(move || {
+        let reader = BufReader::new(pipe);
+        for line in reader.lines() {
+            match line {
+                Ok(line) => push_log_line(&app, stream, line),
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+// Waits for the backend to respond on `/pcc`, polling on an exponential
+// backoff (100ms, doubling, capped at 1s) instead of sleeping a fixed amount.
+// Bails out early with captured log output if the child exits before
+// becoming ready. The child's own stdout/stderr pipes are already owned and
+// drained by `spawn_log_reader` by the time this runs, so the captured
+// output is read back from the shared log buffer instead of the pipes.
+async fn wait_for_ready(app: &AppHandle, child: &mut Child, port: u16) -> Result<(), String> {
+    let deadline = std::time::Instant::now() + startup_timeout();
+    let client = reqwest::Client::new();
+    let test_url = format!("http://127.0.0.1:{}/pcc", port);
+    let mut backoff = Duration::from_millis(100);
+
+    loop {
+        if let Ok(Some(status)) = child.try_wait() {
+            let captured = recent_log_lines(app, 20);
+            return Err(format!(
+                "Backend process exited early with {}: {}",
+                status, captured
+            ));
+        }
+
+        if client
+            .get(&test_url)
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+
+        if std::time::Instant::now() >= deadline {
+            return Err(format!(
+                "Backend server did not respond within {:?}",
+                startup_timeout()
+            ));
+        }
+
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(Duration::from_secs(1));
+    }
+}
+
+// Checks whether whatever is listening on the default port is actually our
+// backend, rather than some unrelated service (a stray dev server, another
+// app) that happens to also be bound to it. Our `/pcc` always answers with
+// 200 and a JSON object body, so anything else is treated as "not ours".
+async fn probe_is_our_backend(client: &reqwest::Client) -> bool {
+    let response = match client
+        .get(format!("http://127.0.0.1:{}/pcc", DEFAULT_PORT))
+        .timeout(Duration::from_secs(2))
+        .send()
+        .await
+    {
+        Ok(resp) => resp,
+        Err(_) => return false,
+    };
+
+    if response.status() != reqwest::StatusCode::OK {
+        return false;
+    }
+
+    matches!(
+        response.json::<serde_json::Value>().await,
+        Ok(serde_json::Value::Object(_))
+    )
+}
+
+// Starts the Python backend, or adopts an already-running one on the expected port.
+#[tracing::instrument(skip(app))]
+pub async fn start_backend(app: &AppHandle) -> Result<(), String> {
+    let backend_state = state(app);
+    if backend_state.started.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    // First, check if a backend is already running on the default port, and
+    // that it's actually ours before adopting it.
+    let client = reqwest::Client::new();
+    if probe_is_our_backend(&client).await {
+        tracing::info!("Backend is already running - using existing backend");
+        backend_state.port.store(DEFAULT_PORT, Ordering::Relaxed);
+        backend_state.started.store(true, Ordering::Relaxed);
+        metrics::set_backend_up(true);
+        emit_status(app, "ready", None);
+        return Ok(());
+    }
+    tracing::info!("Backend not running on the default port, starting Python backend server");
+
+    emit_status(app, "starting", None);
+    let backend_path = backend_directory()?;
+    tracing::debug!(path = ?backend_path, "Using backend path");
+
+    let port = pick_port()?;
+    if port != DEFAULT_PORT {
+        tracing::info!(
+            default_port = DEFAULT_PORT,
+            chosen_port = port,
+            "Default port occupied by another process, using a fresh port"
+        );
+    }
+    backend_state.port.store(port, Ordering::Relaxed);
+
+    let mut child = spawn_backend_process(&backend_path, port)?;
+    spawn_log_reader(app.clone(), "stdout", child.stdout.take().unwrap());
+    spawn_log_reader(app.clone(), "stderr", child.stderr.take().unwrap());
+
+    let ready = wait_for_ready(app, &mut child, port).await;
+
+    {
+        // Kill off whatever was previously in the slot before replacing it —
+        // otherwise a child from an earlier, timed-out attempt (which may be
+        // listening on a different port the reuse probe never checks) leaks.
+        let mut process_guard = backend_state.child.lock().unwrap();
+        if let Some(mut previous_child) = process_guard.take() {
+            let _ = previous_child.kill();
+            let _ = previous_child.wait();
+        }
+        *process_guard = Some(child);
+    }
+
+    match ready {
+        Ok(()) => {
+            backend_state.started.store(true, Ordering::Relaxed);
+            backend_state.restart_count.store(0, Ordering::Relaxed);
+            tracing::info!("Backend server started successfully and is responding");
+            metrics::set_backend_up(true);
+            emit_status(app, "ready", None);
+            spawn_watchdog(app.clone());
+            Ok(())
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Backend server failed to start or is not responding");
+            metrics::set_backend_up(false);
+            emit_status(app, "crashed", Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+// Periodically checks that the backend child is still alive; if it exited
+// without us asking it to (via `cleanup_backend`), restarts it with capped
+// exponential backoff up to `MAX_RESTART_ATTEMPTS` attempts. A restart
+// attempt that itself fails (e.g. it times out again) does not end
+// supervision - it keeps retrying here until it succeeds or the attempt
+// ceiling is reached.
+fn spawn_watchdog(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(2));
+
+        let backend_state = state(&app);
+        if backend_state.shutting_down.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let exited = {
+            let mut guard = backend_state.child.lock().unwrap();
+            match guard.as_mut() {
+                Some(child) => matches!(child.try_wait(), Ok(Some(_))),
+                None => false,
+            }
+        };
+
+        if !exited {
+            continue;
+        }
+
+        backend_state.started.store(false, Ordering::Relaxed);
+        metrics::set_backend_up(false);
+
+        loop {
+            if backend_state.shutting_down.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let attempt = backend_state.restart_count.fetch_add(1, Ordering::Relaxed) + 1;
+            if attempt > MAX_RESTART_ATTEMPTS {
+                tracing::error!(attempts = MAX_RESTART_ATTEMPTS, "Backend crashed and exceeded restart attempts, giving up");
+                emit_status(&app, "gave-up", Some(format!("{} restart attempts exhausted", MAX_RESTART_ATTEMPTS)));
+                return;
+            }
+
+            tracing::warn!(attempt, max_attempts = MAX_RESTART_ATTEMPTS, "Backend crashed, restarting");
+            metrics::record_restart();
+            emit_status(&app, "crashed", Some(format!("restarting, attempt {}/{}", attempt, MAX_RESTART_ATTEMPTS)));
+
+            let backoff = Duration::from_millis(500).saturating_mul(1 << attempt.min(6));
+            thread::sleep(backoff.min(Duration::from_secs(30)));
+
+            match tauri::async_runtime::block_on(start_backend(&app)) {
+                Ok(()) => {
+                    // `start_backend` already spawned a fresh watchdog for
+                    // the new child; this thread's job is done.
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, attempt, "Restart attempt failed, retrying");
+                }
+            }
+        }
+    });
+}
+
+// Cleans up the backend process on app shutdown.
+pub fn cleanup_backend(app: &AppHandle) {
+    tracing::info!("Cleaning up backend process...");
+    let backend_state = state(app);
+    backend_state.shutting_down.store(true, Ordering::Relaxed);
+    {
+        let mut process_guard = backend_state.child.lock().unwrap();
+        if let Some(mut child) = process_guard.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+            tracing::info!("Backend process terminated");
+        }
+    }
+    backend_state.started.store(false, Ordering::Relaxed);
+    metrics::set_backend_up(false);
+}
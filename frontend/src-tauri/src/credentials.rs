@@ -0,0 +1,119 @@
+// In-memory credential store: the frontend unlocks it once with a passphrase
+// (hashed with argon2) and `call_backend_api` reads the auth token from here
+// afterwards, instead of having it threaded through every invocation as a
+// plaintext argument.
+
+use std::sync::Mutex;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use tauri::{AppHandle, Manager};
+
+#[derive(Default)]
+struct Inner {
+    // PHC-format argon2 hash of the unlock passphrase, set on first unlock.
+    passphrase_hash: Option<String>,
+    // The backend auth token, cached only once the store is unlocked.
+    token: Option<String>,
+}
+
+/// Managed via `app.manage(CredentialStore::default())`.
+#[derive(Default)]
+pub struct CredentialStore(Mutex<Inner>);
+
+impl CredentialStore {
+    /// Unlocks the store for this session: on first use, the passphrase is
+    /// hashed and remembered; on subsequent uses it must match. Either way,
+    /// the backend token is cached in memory for `call_backend_api` to reuse.
+    pub fn unlock(&self, passphrase: &str, token: String) -> Result<(), String> {
+        let mut inner = self.0.lock().unwrap();
+
+        match &inner.passphrase_hash {
+            Some(hash) => {
+                let parsed = PasswordHash::new(hash)
+                    .map_err(|e| format!("Corrupt credential store: {}", e))?;
+                Argon2::default()
+                    .verify_password(passphrase.as_bytes(), &parsed)
+                    .map_err(|_| "Incorrect passphrase".to_string())?;
+            }
+            None => {
+                let salt = SaltString::generate(&mut OsRng);
+                let hash = Argon2::default()
+                    .hash_password(passphrase.as_bytes(), &salt)
+                    .map_err(|e| format!("Failed to hash passphrase: {}", e))?
+                    .to_string();
+                inner.passphrase_hash = Some(hash);
+            }
+        }
+
+        inner.token = Some(token);
+        Ok(())
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.0.lock().unwrap().token.clone()
+    }
+
+    pub fn lock(&self) {
+        self.0.lock().unwrap().token = None;
+    }
+}
+
+fn store(app: &AppHandle) -> tauri::State<'_, CredentialStore> {
+    app.state::<CredentialStore>()
+}
+
+#[tauri::command]
+pub fn unlock_credentials(
+    app: AppHandle,
+    passphrase: String,
+    token: String,
+) -> Result<(), String> {
+    store(&app).unlock(&passphrase, token)
+}
+
+#[tauri::command]
+pub fn lock_credentials(app: AppHandle) {
+    store(&app).lock();
+}
+
+/// Returns the cached backend token, if the store has been unlocked.
+pub fn current_token(app: &AppHandle) -> Option<String> {
+    store(app).token()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_unlock_with_correct_passphrase_succeeds() {
+        let store = CredentialStore::default();
+        store.unlock("hunter2", "token-1".to_string()).unwrap();
+        store.unlock("hunter2", "token-2".to_string()).unwrap();
+        assert_eq!(store.token(), Some("token-2".to_string()));
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected_without_clobbering_cached_token() {
+        let store = CredentialStore::default();
+        store.unlock("hunter2", "token-1".to_string()).unwrap();
+
+        let result = store.unlock("wrong-passphrase", "token-2".to_string());
+
+        assert!(result.is_err());
+        assert_eq!(store.token(), Some("token-1".to_string()));
+    }
+
+    #[test]
+    fn lock_clears_the_cached_token() {
+        let store = CredentialStore::default();
+        store.unlock("hunter2", "token-1".to_string()).unwrap();
+        assert!(store.token().is_some());
+
+        store.lock();
+
+        assert_eq!(store.token(), None);
+    }
+}
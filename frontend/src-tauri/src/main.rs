@@ -1,12 +1,17 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod backend;
+mod credentials;
+mod metrics;
+mod transfer;
+
 use serde::{Deserialize, Serialize};
-use std::process::{Command, Stdio};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Mutex;
-use std::thread;
-use std::time::Duration;
+use std::time::Instant;
+
+use backend::BackendState;
+use credentials::CredentialStore;
+use tauri::Manager;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ApiResponse {
@@ -15,149 +20,51 @@ struct ApiResponse {
     error: Option<String>,
 }
 
-// Global state for backend process
-static BACKEND_STARTED: AtomicBool = AtomicBool::new(false);
-static BACKEND_PROCESS: Mutex<Option<std::process::Child>> = Mutex::new(None);
-
-// Function to start the Python backend
-async fn start_backend() -> Result<(), String> {
-    if BACKEND_STARTED.load(Ordering::Relaxed) {
-        return Ok(());
-    }
-
-    // First, check if backend is already running
-    let client = reqwest::Client::new();
-    match client.get("http://127.0.0.1:8000/pcc").timeout(Duration::from_secs(2)).send().await {
-        Ok(_) => {
-            println!("Backend is already running - using existing backend");
-            BACKEND_STARTED.store(true, Ordering::Relaxed);
-            return Ok(());
-        }
-        Err(e) => {
-            println!("Backend not running ({}), starting Python backend server...", e);
-        }
-    }
-
-    // Get the backend directory path - try multiple possible locations
-    let possible_paths = vec![
-        // Bundled backend path (in app bundle)
-        std::env::current_dir()
-            .unwrap()
-            .join("Contents/Resources/backend"),
-        // Development path
-        std::env::current_dir()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .join("backend"),
-        // Built app path
-        std::env::current_dir().unwrap().join("backend"),
-        // Alternative built app path
-        std::env::current_dir().unwrap().join("../backend"),
-    ];
-
-    let backend_path = possible_paths
-        .iter()
-        .find(|path| path.exists())
-        .ok_or("Backend directory not found. Please ensure the backend folder exists.")?;
-
-    println!("Using backend path: {:?}", backend_path);
-
-    // Determine Python command
-    let python_cmd = if cfg!(target_os = "windows") {
-        "python"
-    } else {
-        "python3"
-    };
-
-    // Start the backend server
-    let child = Command::new(python_cmd)
-        .arg("-m")
-        .arg("uvicorn")
-        .arg("app.main:app")
-        .arg("--host")
-        .arg("127.0.0.1")
-        .arg("--port")
-        .arg("8000")
-        .current_dir(&backend_path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| {
-            format!(
-                "Failed to start backend: {}. Make sure Python and uvicorn are installed.",
-                e
-            )
-        })?;
-
-    {
-        let mut process_guard = BACKEND_PROCESS.lock().unwrap();
-        *process_guard = Some(child);
-    }
-
-    BACKEND_STARTED.store(true, Ordering::Relaxed);
-
-    // Wait for server to start and check if it's actually running
-    thread::sleep(Duration::from_secs(3));
-
-    // Test if the backend is actually running
-    let client = reqwest::Client::new();
-    let test_url = "http://127.0.0.1:8000/pcc";
-
-    match client
-        .get(test_url)
-        .timeout(Duration::from_secs(5))
-        .send()
-        .await
-    {
-        Ok(_) => {
-            println!("Backend server started successfully and is responding");
-            Ok(())
-        }
-        Err(e) => {
-            println!("Backend server failed to start or is not responding: {}", e);
-            Err(format!("Backend server is not responding: {}", e))
-        }
-    }
+fn is_json_content_type(resp: &reqwest::Response) -> bool {
+    resp.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.starts_with("application/json"))
+        .unwrap_or(true)
 }
 
-// Function to cleanup backend process
-fn cleanup_backend() {
-    println!("Cleaning up backend process...");
-    {
-        let mut process_guard = BACKEND_PROCESS.lock().unwrap();
-        if let Some(mut child) = process_guard.take() {
-            let _ = child.kill();
-            let _ = child.wait();
-            println!("Backend process terminated");
-        }
+fn apply_auth_header(
+    request_builder: reqwest::RequestBuilder,
+    token: Option<&str>,
+) -> reqwest::RequestBuilder {
+    if let Some(token) = token {
+        // Clean the token - remove any quotes that might be present. Never log
+        // the token itself, even at debug level.
+        let clean_token = token.trim_matches('"');
+        tracing::debug!("Adding auth header (token redacted)");
+        request_builder.header("Authorization", format!("Bearer {}", clean_token))
+    } else {
+        tracing::debug!("No auth token provided");
+        request_builder
     }
-    BACKEND_STARTED.store(false, Ordering::Relaxed);
 }
 
 #[tauri::command]
+#[tracing::instrument(skip(app, data, files))]
 async fn call_backend_api(
+    app: tauri::AppHandle,
     endpoint: String,
     data: Option<serde_json::Value>,
-    auth_token: Option<String>,
-    token: Option<String>,
+    files: Option<Vec<String>>,
 ) -> Result<ApiResponse, String> {
-    println!("=== Rust API Call Debug ===");
-    println!("Endpoint: {}", endpoint);
-    println!("Data provided: {}", data.is_some());
-    println!("Auth token provided: {}", auth_token.is_some());
-    println!("Auth token value: {:?}", auth_token);
-    println!("Token provided: {}", token.is_some());
-    println!("Token value: {:?}", token);
-    println!("==========================");
+    tracing::debug!(
+        data_provided = data.is_some(),
+        files_provided = files.as_ref().map(|f| f.len()).unwrap_or(0),
+        "Handling backend API call"
+    );
 
     // Start the backend if it's not running
-    match start_backend().await {
+    match backend::start_backend(&app).await {
         Ok(_) => {
-            println!("Backend is ready");
+            tracing::debug!("Backend is ready");
         }
         Err(e) => {
-            println!("Failed to start backend: {}", e);
+            tracing::warn!(error = %e, "Failed to start backend");
             return Ok(ApiResponse {
                 success: false,
                 data: None,
@@ -166,12 +73,19 @@ async fn call_backend_api(
         }
     }
 
-    // Make HTTP request to the backend
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-    let url = format!("http://127.0.0.1:8000{}", endpoint);
+    let token = credentials::current_token(&app);
+    let client = app.state::<BackendState>().client.clone();
+    let url = format!("{}{}", backend::base_url(&app), endpoint);
+
+    // File uploads go out as multipart, independent of the _method dance below.
+    if let Some(file_paths) = files.filter(|f| !f.is_empty()) {
+        let form = transfer::build_multipart_form(data.as_ref(), &file_paths).await?;
+        let request_builder = apply_auth_header(client.post(&url), token.as_deref());
+        tracing::debug!(%url, "Sending multipart POST request");
+        let started = Instant::now();
+        let response = request_builder.multipart(form).send().await;
+        return handle_response(&app, &endpoint, "POST", started, response).await;
+    }
 
     // Determine request method and prepare data
     let (request_builder, method, clean_data) = if let Some(request_data) = data {
@@ -199,24 +113,23 @@ async fn call_backend_api(
     };
 
     // Add authentication header if provided
-    let token_to_use = auth_token.as_ref().or(token.as_ref());
-    let request_builder = if let Some(token) = token_to_use {
-        // Clean the token - remove any quotes that might be present
-        let clean_token = token.trim_matches('"');
-        println!("Adding auth header: Bearer {}", clean_token);
-        request_builder.header("Authorization", format!("Bearer {}", clean_token))
-    } else {
-        println!("No auth token provided");
-        request_builder
-    };
+    let request_builder = apply_auth_header(request_builder, token.as_deref());
 
+    let method_label = method.clone().unwrap_or_else(|| {
+        if clean_data.is_some() {
+            "POST".to_string()
+        } else {
+            "GET".to_string()
+        }
+    });
+    let started = Instant::now();
     let response = match method.as_deref() {
         Some("DELETE") => {
-            println!("Sending DELETE request to: {}", url);
+            tracing::debug!(%url, "Sending DELETE request");
             request_builder.send().await
         }
         Some("PUT") => {
-            println!("Sending PUT request to: {}", url);
+            tracing::debug!(%url, "Sending PUT request");
             if let Some(data) = clean_data {
                 request_builder.json(&data).send().await
             } else {
@@ -225,68 +138,163 @@ async fn call_backend_api(
         }
         _ => {
             if let Some(data) = clean_data {
-                println!("Sending POST request to: {}", url);
+                tracing::debug!(%url, "Sending POST request");
                 request_builder.json(&data).send().await
             } else {
-                println!("Sending GET request to: {}", url);
+                tracing::debug!(%url, "Sending GET request");
                 request_builder.send().await
             }
         }
     };
 
-    match response {
+    handle_response(&app, &endpoint, &method_label, started, response).await
+}
+
+async fn handle_response(
+    app: &tauri::AppHandle,
+    endpoint: &str,
+    method: &str,
+    started: Instant,
+    response: Result<reqwest::Response, reqwest::Error>,
+) -> Result<ApiResponse, String> {
+    let latency_secs = started.elapsed().as_secs_f64();
+    let result = match response {
         Ok(resp) => {
             let status = resp.status();
             if status.is_success() {
-                let data = resp
-                    .json::<serde_json::Value>()
-                    .await
-                    .map_err(|e| e.to_string())?;
-                Ok(ApiResponse {
-                    success: true,
-                    data: Some(data),
-                    error: None,
-                })
+                if is_json_content_type(&resp) {
+                    let data = resp
+                        .json::<serde_json::Value>()
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    (
+                        "success",
+                        Ok(ApiResponse {
+                            success: true,
+                            data: Some(data),
+                            error: None,
+                        }),
+                    )
+                } else {
+                    // Non-JSON bodies (file/report artifacts) are streamed to a
+                    // temp file instead of being buffered in memory.
+                    let data = transfer::stream_response_to_file(app, endpoint, resp).await?;
+                    (
+                        "success",
+                        Ok(ApiResponse {
+                            success: true,
+                            data: Some(data),
+                            error: None,
+                        }),
+                    )
+                }
             } else {
                 let error_text = resp.text().await.unwrap_or_default();
-                Ok(ApiResponse {
-                    success: false,
-                    data: None,
-                    error: Some(format!("HTTP {}: {}", status, error_text)),
-                })
+                (
+                    "http_error",
+                    Ok(ApiResponse {
+                        success: false,
+                        data: None,
+                        error: Some(format!("HTTP {}: {}", status, error_text)),
+                    }),
+                )
             }
         }
-        Err(e) => Ok(ApiResponse {
-            success: false,
-            data: None,
-            error: Some(e.to_string()),
-        }),
+        Err(e) => (
+            "transport_error",
+            Ok(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+            }),
+        ),
+    };
+
+    let (outcome, response) = result;
+    metrics::record_request(endpoint, method, outcome, latency_secs);
+    tracing::debug!(endpoint, method, outcome, latency_secs, "Backend request completed");
+    response
+}
+
+// Companion command for downloading a backend artifact with a plain GET.
+// Non-JSON bodies are streamed to disk with chunked progress events; a JSON
+// body (or one with no Content-Type) is returned inline, as in
+// `call_backend_api`, and is not written to disk.
+#[tauri::command]
+async fn download_backend_file(
+    app: tauri::AppHandle,
+    endpoint: String,
+) -> Result<ApiResponse, String> {
+    match backend::start_backend(&app).await {
+        Ok(_) => {}
+        Err(e) => {
+            return Ok(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(format!("Backend server is not available. Error: {}", e)),
+            });
+        }
     }
+
+    let token = credentials::current_token(&app);
+    let client = app.state::<BackendState>().client.clone();
+    let url = format!("{}{}", backend::base_url(&app), endpoint);
+    let request_builder = apply_auth_header(client.get(&url), token.as_deref());
+
+    tracing::debug!(%url, "Downloading backend artifact");
+    let started = Instant::now();
+    let response = request_builder.send().await;
+    handle_response(&app, &endpoint, "GET", started, response).await
+}
+
+// Returns a snapshot of the proxy's metrics in Prometheus text format, for
+// frontends that can't reach the local :9091 scrape endpoint directly.
+#[tauri::command]
+fn metrics_snapshot() -> String {
+    metrics::render_prometheus()
 }
 
 fn main() {
-    // Set up signal handlers for cleanup
-    ctrlc::set_handler(|| {
-        cleanup_backend();
-        std::process::exit(0);
-    }).expect("Error setting Ctrl+C handler");
+    tracing_subscriber::fmt::init();
+    metrics::start_metrics_server();
 
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![call_backend_api])
-        .setup(|_app| {
+    let app = tauri::Builder::default()
+        .manage(BackendState::new())
+        .manage(CredentialStore::default())
+        .invoke_handler(tauri::generate_handler![
+            call_backend_api,
+            download_backend_file,
+            metrics_snapshot,
+            credentials::unlock_credentials,
+            credentials::lock_credentials
+        ])
+        .setup(|app| {
             // Start the backend when the app launches
-            tauri::async_runtime::spawn(async {
-                if let Err(e) = start_backend().await {
-                    eprintln!("Failed to start backend: {}", e);
+            let handle = app.handle();
+            transfer::cleanup_stale_downloads(&handle);
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = backend::start_backend(&handle).await {
+                    tracing::error!(error = %e, "Failed to start backend");
                 }
             });
             Ok(())
         })
         .on_window_event(|event| {
             if let tauri::WindowEvent::CloseRequested { .. } = event.event() {
-                cleanup_backend();
+                backend::cleanup_backend(&event.window().app_handle());
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application");
+
+    // Set up signal handlers for cleanup, now that we have a handle into the
+    // managed app state.
+    let handle = app.handle();
+    ctrlc::set_handler(move || {
+        backend::cleanup_backend(&handle);
+        std::process::exit(0);
+    })
+    .expect("Error setting Ctrl+C handler");
+
+    app.run(|_app_handle, _event| {});
 }
@@ -0,0 +1,184 @@
+// Minimal Prometheus-style metrics for the backend proxy layer: request
+// counters, a latency histogram and a couple of backend-health gauges.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+const LATENCY_BUCKETS: [f64; 7] = [0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0];
+
+// Cumulative per-bucket counts plus a running sum/count, updated once per
+// request. This is how a Prometheus histogram is meant to work - fixed
+// memory per (endpoint, method) pair, rather than retaining every raw
+// sample forever.
+#[derive(Default)]
+struct LatencyHistogram {
+    bucket_counts: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency_secs: f64) {
+        for (bucket_count, threshold) in self.bucket_counts.iter_mut().zip(LATENCY_BUCKETS) {
+            if latency_secs <= threshold {
+                *bucket_count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += latency_secs;
+    }
+}
+
+static REQUEST_COUNTS: Mutex<Option<HashMap<(String, String, String), u64>>> = Mutex::new(None);
+static LATENCY_HISTOGRAMS: Mutex<Option<HashMap<(String, String), LatencyHistogram>>> =
+    Mutex::new(None);
+static BACKEND_UP: AtomicBool = AtomicBool::new(false);
+static RESTART_COUNT: AtomicU64 = AtomicU64::new(0);
+
+fn with_counts<R>(f: impl FnOnce(&mut HashMap<(String, String, String), u64>) -> R) -> R {
+    let mut guard = REQUEST_COUNTS.lock().unwrap();
+    f(guard.get_or_insert_with(HashMap::new))
+}
+
+fn with_latencies<R>(f: impl FnOnce(&mut HashMap<(String, String), LatencyHistogram>) -> R) -> R {
+    let mut guard = LATENCY_HISTOGRAMS.lock().unwrap();
+    f(guard.get_or_insert_with(HashMap::new))
+}
+
+// Replaces path segments that look like an embedded id (e.g. `/reports/42`
+// or `/reports/3fa8...-uuid`) with a placeholder, so a raw path containing a
+// different id on every call doesn't grow the metrics maps without bound -
+// callers aren't required to pass only route templates.
+fn normalize_endpoint(endpoint: &str) -> String {
+    endpoint
+        .split('/')
+        .map(|segment| if looks_like_id(segment) { ":id" } else { segment })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn looks_like_id(segment: &str) -> bool {
+    if segment.is_empty() {
+        return false;
+    }
+    let is_numeric = segment.chars().all(|c| c.is_ascii_digit());
+    let is_uuid_like =
+        segment.len() >= 16 && segment.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    is_numeric || is_uuid_like
+}
+
+// Records one completed proxy request: its endpoint, HTTP method, outcome
+// (e.g. "success", "http_error", "transport_error") and wall-clock latency.
+pub fn record_request(endpoint: &str, method: &str, outcome: &str, latency_secs: f64) {
+    let endpoint = normalize_endpoint(endpoint);
+    with_counts(|counts| {
+        *counts
+            .entry((endpoint.clone(), method.to_string(), outcome.to_string()))
+            .or_insert(0) += 1;
+    });
+    with_latencies(|hist| {
+        hist.entry((endpoint, method.to_string()))
+            .or_insert_with(LatencyHistogram::default)
+            .record(latency_secs);
+    });
+}
+
+pub fn set_backend_up(up: bool) {
+    BACKEND_UP.store(up, Ordering::Relaxed);
+}
+
+pub fn record_restart() {
+    RESTART_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+// Renders all metrics in Prometheus text exposition format.
+pub fn render_prometheus() -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP bendbionics_proxy_requests_total Total backend proxy requests.\n");
+    out.push_str("# TYPE bendbionics_proxy_requests_total counter\n");
+    with_counts(|counts| {
+        for ((endpoint, method, outcome), count) in counts.iter() {
+            out.push_str(&format!(
+                "bendbionics_proxy_requests_total{{endpoint=\"{}\",method=\"{}\",outcome=\"{}\"}} {}\n",
+                endpoint, method, outcome, count
+            ));
+        }
+    });
+
+    out.push_str("# HELP bendbionics_proxy_request_duration_seconds Backend proxy request latency.\n");
+    out.push_str("# TYPE bendbionics_proxy_request_duration_seconds histogram\n");
+    with_latencies(|hist| {
+        for ((endpoint, method), histogram) in hist.iter() {
+            for (bucket_count, threshold) in histogram.bucket_counts.iter().zip(LATENCY_BUCKETS) {
+                out.push_str(&format!(
+                    "bendbionics_proxy_request_duration_seconds_bucket{{endpoint=\"{}\",method=\"{}\",le=\"{}\"}} {}\n",
+                    endpoint, method, threshold, bucket_count
+                ));
+            }
+            out.push_str(&format!(
+                "bendbionics_proxy_request_duration_seconds_bucket{{endpoint=\"{}\",method=\"{}\",le=\"+Inf\"}} {}\n",
+                endpoint, method, histogram.count
+            ));
+            out.push_str(&format!(
+                "bendbionics_proxy_request_duration_seconds_sum{{endpoint=\"{}\",method=\"{}\"}} {}\n",
+                endpoint, method, histogram.sum
+            ));
+            out.push_str(&format!(
+                "bendbionics_proxy_request_duration_seconds_count{{endpoint=\"{}\",method=\"{}\"}} {}\n",
+                endpoint, method, histogram.count
+            ));
+        }
+    });
+
+    out.push_str("# HELP bendbionics_backend_up Whether the embedded backend is currently reachable.\n");
+    out.push_str("# TYPE bendbionics_backend_up gauge\n");
+    out.push_str(&format!(
+        "bendbionics_backend_up {}\n",
+        if BACKEND_UP.load(Ordering::Relaxed) { 1 } else { 0 }
+    ));
+
+    out.push_str("# HELP bendbionics_backend_restarts_total Backend restarts performed by the watchdog.\n");
+    out.push_str("# TYPE bendbionics_backend_restarts_total counter\n");
+    out.push_str(&format!(
+        "bendbionics_backend_restarts_total {}\n",
+        RESTART_COUNT.load(Ordering::Relaxed)
+    ));
+
+    out
+}
+
+fn handle_metrics_connection(mut stream: TcpStream) {
+    let mut buf = [0u8; 1024];
+    let _ = stream.read(&mut buf);
+
+    let body = render_prometheus();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+// Serves `GET /metrics` in Prometheus text format on 127.0.0.1:9091 for local
+// scraping. Best-effort: if the port is already taken (e.g. a second app
+// instance), metrics are still available via the `metrics_snapshot` command.
+pub fn start_metrics_server() {
+    thread::spawn(|| {
+        let listener = match TcpListener::bind("127.0.0.1:9091") {
+            Ok(l) => l,
+            Err(e) => {
+                tracing::warn!(error = %e, "Metrics endpoint disabled, failed to bind 127.0.0.1:9091");
+                return;
+            }
+        };
+        for stream in listener.incoming().flatten() {
+            handle_metrics_connection(stream);
+        }
+    });
+}
@@ -0,0 +1,178 @@
+// Streaming and multipart helpers for large uploads/downloads to/from the backend.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_util::StreamExt;
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+
+// Downloaded artifacts may be clinical reports or model files, so they're
+// kept in a private per-app directory rather than the shared, world-readable
+// OS temp dir, and are cleaned up after this long.
+const MAX_DOWNLOAD_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, serde::Serialize)]
+struct DownloadProgressEvent {
+    endpoint: String,
+    bytes_downloaded: u64,
+    total_bytes: Option<u64>,
+}
+
+// Builds a multipart form out of the (optional) JSON data fields and a list
+// of local file paths the frontend wants uploaded.
+pub async fn build_multipart_form(
+    data: Option<&serde_json::Value>,
+    file_paths: &[String],
+) -> Result<reqwest::multipart::Form, String> {
+    let mut form = reqwest::multipart::Form::new();
+
+    if let Some(serde_json::Value::Object(fields)) = data {
+        for (key, value) in fields {
+            // `_method` is an override consumed by `call_backend_api` to pick
+            // the HTTP verb - like the JSON branch, it must not leak into the
+            // request body itself.
+            if key == "_method" {
+                continue;
+            }
+            let text = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            form = form.text(key.clone(), text);
+        }
+    }
+
+    for path in file_paths {
+        let file_name = std::path::Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "upload".to_string());
+        form = form
+            .file(file_name, path)
+            .await
+            .map_err(|e| format!("Failed to attach file {}: {}", path, e))?;
+    }
+
+    Ok(form)
+}
+
+// Private, per-app directory for downloaded artifacts - falls back to the
+// shared OS temp dir only if the app's own cache dir isn't resolvable.
+fn downloads_dir(app: &AppHandle) -> PathBuf {
+    let base = app
+        .path_resolver()
+        .app_cache_dir()
+        .unwrap_or_else(std::env::temp_dir);
+    let dir = base.join("backend-downloads");
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+fn temp_file_path(app: &AppHandle, content_type: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let ext = content_type
+        .split('/')
+        .last()
+        .filter(|s| !s.is_empty() && s.len() < 10)
+        .unwrap_or("bin");
+    downloads_dir(app).join(format!("bendbionics-download-{}.{}", nanos, ext))
+}
+
+// Opens `path` for writing with owner-only permissions on unix (0o600), so
+// downloaded artifacts aren't world-readable even if they land on a shared
+// filesystem.
+#[cfg(unix)]
+async fn create_private_file(path: &Path) -> Result<tokio::fs::File, String> {
+    use std::os::unix::fs::OpenOptionsExt;
+    tokio::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .await
+        .map_err(|e| format!("Failed to create temp file: {}", e))
+}
+
+#[cfg(not(unix))]
+async fn create_private_file(path: &Path) -> Result<tokio::fs::File, String> {
+    tokio::fs::File::create(path)
+        .await
+        .map_err(|e| format!("Failed to create temp file: {}", e))
+}
+
+// Removes downloaded artifacts older than `MAX_DOWNLOAD_AGE` so they don't
+// accumulate indefinitely. Called once at app startup; best-effort.
+pub fn cleanup_stale_downloads(app: &AppHandle) {
+    let dir = downloads_dir(app);
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .map(|age| age > MAX_DOWNLOAD_AGE)
+            .unwrap_or(false);
+        if is_stale {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!(path = ?path, error = %e, "Failed to remove stale download");
+            }
+        }
+    }
+}
+
+// Streams a non-JSON response body to a temp file in chunks, emitting
+// `download-progress` events as it goes, and returns metadata describing
+// where the file landed rather than inlining the body.
+pub async fn stream_response_to_file(
+    app: &AppHandle,
+    endpoint: &str,
+    resp: reqwest::Response,
+) -> Result<serde_json::Value, String> {
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let total_bytes = resp.content_length();
+
+    let path = temp_file_path(app, &content_type);
+    let mut file = create_private_file(&path).await?;
+
+    let mut downloaded: u64 = 0;
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download stream error: {}", e))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write temp file: {}", e))?;
+        downloaded += chunk.len() as u64;
+        let _ = app.emit_all(
+            "download-progress",
+            DownloadProgressEvent {
+                endpoint: endpoint.to_string(),
+                bytes_downloaded: downloaded,
+                total_bytes,
+            },
+        );
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+
+    Ok(serde_json::json!({
+        "file_path": path.to_string_lossy(),
+        "content_type": content_type,
+        "size": downloaded,
+    }))
+}